@@ -0,0 +1,70 @@
+mod values;
+
+pub use values::sqlite::{SQLiteDatabase, MEMORY_DB};
+
+use nu_protocol::{engine::EngineState, ShellError, Span};
+use rusqlite::Connection;
+use std::sync::{Arc, Mutex};
+
+/// A `stor` database resolved from `--database`, the active `stor open`
+/// backend, or the default in-memory store, plus whether it's safe to write
+/// to.
+pub struct ResolvedStorTarget {
+    pub db: Box<SQLiteDatabase>,
+    pub conn: Option<Arc<Mutex<Connection>>>,
+    /// `true` only when the active `stor open` backend was opened with
+    /// `--read-only`. Named and default stores are always writable.
+    pub read_only: bool,
+}
+
+/// Resolve the `stor` database that a command should operate on: a named
+/// in-memory database if `--database` was given, the active `stor open`
+/// backend if one was set up earlier this session, or the default in-memory
+/// store otherwise. Shared by every `stor` subcommand that reads or writes
+/// the database, so they can't drift on how this resolution works.
+pub fn resolve_stor_target(
+    engine_state: &EngineState,
+    database_name: Option<&str>,
+    span: Span,
+) -> Result<ResolvedStorTarget, ShellError> {
+    match database_name {
+        Some(name) => {
+            let shared = engine_state.stor_connection(name).map_err(|err| {
+                ShellError::GenericError(
+                    format!("Failed to open stor database {name}"),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+            let uri = format!("file:{name}?mode=memory&cache=shared");
+            let db = Box::new(SQLiteDatabase::new(std::path::Path::new(&uri), None));
+            Ok(ResolvedStorTarget {
+                db,
+                conn: Some(shared),
+                read_only: false,
+            })
+        }
+        None => match engine_state.stor_active() {
+            // `stor open` was run earlier this session: its file-backed
+            // connection is the active store.
+            Some(active) => {
+                let db = Box::new(SQLiteDatabase::new(&active.path, None));
+                Ok(ResolvedStorTarget {
+                    db,
+                    conn: Some(active.conn),
+                    read_only: active.read_only,
+                })
+            }
+            None => {
+                let db = Box::new(SQLiteDatabase::new(std::path::Path::new(MEMORY_DB), None));
+                Ok(ResolvedStorTarget {
+                    db,
+                    conn: None,
+                    read_only: false,
+                })
+            }
+        },
+    }
+}
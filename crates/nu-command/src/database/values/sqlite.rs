@@ -0,0 +1,308 @@
+use std::fmt::{self, Debug, Formatter};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use nu_protocol::{CustomValue, ShellError, Span, Value};
+use rusqlite::{
+    backup::{Backup, StepResult},
+    Connection, OpenFlags,
+};
+
+/// URI for the shared-cache in-memory database that backs the `stor` family
+/// of commands by default.
+pub const MEMORY_DB: &str = "file::memory:?cache=shared";
+
+/// Schema version written to `nu_stor_meta` by `write_schema_version`, bumped
+/// whenever the on-disk format changes so old databases can be migrated
+/// forward instead of silently misread. It is written only when
+/// `stor open --recover` or `stor import --recover` starts a fresh database;
+/// databases created any other way carry no `nu_stor_meta` table.
+pub const STOR_SCHEMA_VERSION: i64 = 1;
+
+#[derive(Clone)]
+pub struct SQLiteDatabase {
+    pub path: PathBuf,
+    pub query: Option<String>,
+}
+
+impl SQLiteDatabase {
+    pub fn new(path: &Path, query: Option<String>) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            query,
+        }
+    }
+
+    pub fn open_connection(&self) -> Result<Connection, rusqlite::Error> {
+        self.open_connection_with_flags(OpenFlags::default())
+    }
+
+    /// Open the database with explicit `rusqlite::OpenFlags`, e.g. to open
+    /// an existing file read-only for safe inspection.
+    pub fn open_connection_with_flags(
+        &self,
+        flags: OpenFlags,
+    ) -> Result<Connection, rusqlite::Error> {
+        Connection::open_with_flags(&self.path, flags)
+    }
+
+    /// Copy the in-memory database into a file on disk, one batch of pages
+    /// at a time, reporting progress after each batch.
+    ///
+    /// Using the online backup API instead of `VACUUM INTO` lets a large
+    /// export make progress even while another connection holds the source
+    /// database busy, and gives the caller something to show the user
+    /// instead of blocking silently until the whole copy is done.
+    pub fn export_in_memory_database_to_file(
+        &self,
+        conn: &Connection,
+        path: String,
+        pages_per_step: i32,
+        mut on_progress: impl FnMut(i32, i32),
+    ) -> Result<(), rusqlite::Error> {
+        let mut dst = Connection::open(path)?;
+        // A concurrent reader taking the source's lock should make us wait
+        // and retry, not abort the export.
+        dst.busy_handler(Some(|_retries| true))?;
+
+        let backup = Backup::new(conn, &mut dst)?;
+        loop {
+            match backup.step(pages_per_step)? {
+                StepResult::Done => {
+                    let progress = backup.progress();
+                    on_progress(progress.remaining, progress.pagecount);
+                    break;
+                }
+                StepResult::More => {
+                    let progress = backup.progress();
+                    on_progress(progress.remaining, progress.pagecount);
+                }
+                StepResult::Busy | StepResult::Locked => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run `PRAGMA integrity_check` and report whether the database came
+    /// back clean.
+    pub fn integrity_check(conn: &Connection) -> Result<bool, rusqlite::Error> {
+        let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        Ok(result == "ok")
+    }
+
+    /// Write (or refresh) the `nu_stor_meta` table that records the schema
+    /// version of a freshly created database. Called when `--recover`
+    /// replaces a corrupt database with a fresh one.
+    pub fn write_schema_version(conn: &Connection) -> Result<(), rusqlite::Error> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS nu_stor_meta (version INTEGER NOT NULL)",
+            [],
+        )?;
+        conn.execute("DELETE FROM nu_stor_meta", [])?;
+        conn.execute(
+            "INSERT INTO nu_stor_meta (version) VALUES (?1)",
+            [STOR_SCHEMA_VERSION],
+        )?;
+        Ok(())
+    }
+
+    /// Drop every user table in `conn` and rewrite `nu_stor_meta`, leaving a
+    /// genuinely empty database behind. Used to recover a destination that
+    /// can't simply be swapped for a brand new file, e.g. a named or active
+    /// `stor` backend that already has connections open against it.
+    pub fn reset_database(conn: &Connection) -> Result<(), rusqlite::Error> {
+        let mut table_names = Vec::new();
+        {
+            let mut statement = conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            )?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                table_names.push(row.get::<_, String>(0)?);
+            }
+        }
+
+        for table_name in table_names {
+            conn.execute(&format!("DROP TABLE \"{table_name}\""), [])?;
+        }
+
+        Self::write_schema_version(conn)
+    }
+
+    /// Move a corrupt database file aside (so it isn't lost) and return the
+    /// path it was renamed to. If a `.corrupt` file from an earlier recovery
+    /// already exists, a numbered suffix is used instead of overwriting it.
+    pub fn rename_corrupt_file_aside(path: &Path) -> std::io::Result<PathBuf> {
+        let base_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "nudb.sqlite".to_string());
+
+        let mut corrupt_path = path.with_file_name(format!("{base_name}.corrupt"));
+        let mut attempt = 1;
+        while corrupt_path.exists() {
+            corrupt_path = path.with_file_name(format!("{base_name}.corrupt.{attempt}"));
+            attempt += 1;
+        }
+
+        std::fs::rename(path, &corrupt_path)?;
+        Ok(corrupt_path)
+    }
+}
+
+impl Debug for SQLiteDatabase {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("SQLiteDatabase")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl CustomValue for SQLiteDatabase {
+    fn clone_value(&self, span: Span) -> Value {
+        Value::custom_value(Box::new(self.clone()), span)
+    }
+
+    fn type_name(&self) -> String {
+        "SQLiteDatabase".into()
+    }
+
+    fn to_base_value(&self, span: Span) -> Result<Value, ShellError> {
+        Ok(Value::string(self.path.to_string_lossy(), span))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn temp_path(name: &str) -> PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!(
+            "nu_stor_sqlite_test_{}_{}_{name}",
+            std::process::id(),
+            unique
+        ))
+    }
+
+    #[test]
+    fn export_then_integrity_check_round_trips_data() {
+        let path = temp_path("export.sqlite");
+        let _ = std::fs::remove_file(&path);
+
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute("CREATE TABLE foo (x INTEGER)", [])
+            .expect("create table");
+        conn.execute("INSERT INTO foo (x) VALUES (1), (2), (3)", [])
+            .expect("insert rows");
+
+        let db = SQLiteDatabase::new(&path, None);
+        let mut progress_calls = 0;
+        db.export_in_memory_database_to_file(
+            &conn,
+            path.to_string_lossy().into_owned(),
+            1,
+            |_remaining, _pagecount| progress_calls += 1,
+        )
+        .expect("export to file");
+
+        let exported = db.open_connection().expect("open exported file");
+        assert!(SQLiteDatabase::integrity_check(&exported).expect("integrity check"));
+        let count: i64 = exported
+            .query_row("SELECT COUNT(*) FROM foo", [], |row| row.get(0))
+            .expect("count rows");
+        assert_eq!(count, 3);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn integrity_check_flags_a_corrupt_file() {
+        let path = temp_path("corrupt.sqlite");
+        std::fs::write(&path, b"not a sqlite file at all").expect("write garbage file");
+
+        let conn = Connection::open(&path).expect("open garbage file");
+        // The header check surfaces through `integrity_check`, not through
+        // `Connection::open` itself, mirroring how a real corrupt page only
+        // becomes visible once something tries to read it.
+        assert!(!SQLiteDatabase::integrity_check(&conn).unwrap_or(false));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn rename_corrupt_file_aside_does_not_overwrite_a_previous_recovery() {
+        let path = temp_path("recover.sqlite");
+        std::fs::write(&path, b"corrupt-1").expect("write first corrupt file");
+
+        let first = SQLiteDatabase::rename_corrupt_file_aside(&path).expect("first recovery");
+        assert_eq!(
+            first,
+            path.with_file_name(format!(
+                "{}.corrupt",
+                path.file_name().unwrap().to_string_lossy()
+            ))
+        );
+
+        std::fs::write(&path, b"corrupt-2").expect("write second corrupt file");
+        let second = SQLiteDatabase::rename_corrupt_file_aside(&path).expect("second recovery");
+
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read(&first).unwrap(), b"corrupt-1");
+        assert_eq!(std::fs::read(&second).unwrap(), b"corrupt-2");
+
+        std::fs::remove_file(&first).ok();
+        std::fs::remove_file(&second).ok();
+    }
+
+    #[test]
+    fn write_schema_version_is_idempotent() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        SQLiteDatabase::write_schema_version(&conn).expect("write schema version once");
+        SQLiteDatabase::write_schema_version(&conn).expect("write schema version again");
+
+        let version: i64 = conn
+            .query_row("SELECT version FROM nu_stor_meta", [], |row| row.get(0))
+            .expect("read schema version");
+        assert_eq!(version, STOR_SCHEMA_VERSION);
+
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM nu_stor_meta", [], |row| row.get(0))
+            .expect("count schema rows");
+        assert_eq!(rows, 1);
+    }
+
+    #[test]
+    fn reset_database_drops_existing_user_tables() {
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        conn.execute("CREATE TABLE foo (x INTEGER)", [])
+            .expect("create table");
+        conn.execute("INSERT INTO foo (x) VALUES (1)", [])
+            .expect("insert row");
+
+        SQLiteDatabase::reset_database(&conn).expect("reset database");
+
+        let foo_is_gone: rusqlite::Result<i64> =
+            conn.query_row("SELECT COUNT(*) FROM foo", [], |row| row.get(0));
+        assert!(foo_is_gone.is_err());
+
+        let version: i64 = conn
+            .query_row("SELECT version FROM nu_stor_meta", [], |row| row.get(0))
+            .expect("read schema version");
+        assert_eq!(version, STOR_SCHEMA_VERSION);
+    }
+}
@@ -1,10 +1,10 @@
-use crate::database::{SQLiteDatabase, MEMORY_DB};
+use crate::database::resolve_stor_target;
 use nu_engine::CallExt;
 use nu_protocol::{
     ast::Call,
     engine::{Command, EngineState, Stack},
-    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, Span, SyntaxShape,
-    Type, Value,
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, SyntaxShape, Type,
+    Value,
 };
 
 #[derive(Clone)]
@@ -24,6 +24,18 @@ impl Command for StorExport {
                 "file name to export the sqlite in-memory database to",
                 Some('f'),
             )
+            .named(
+                "batch-size",
+                SyntaxShape::Int,
+                "number of pages to copy per backup step (default 100)",
+                Some('b'),
+            )
+            .named(
+                "database",
+                SyntaxShape::String,
+                "name of a named in-memory database to export, instead of the default store",
+                Some('d'),
+            )
             .allow_variants_without_examples(true)
             .category(Category::Math)
     }
@@ -37,11 +49,18 @@ impl Command for StorExport {
     }
 
     fn examples(&self) -> Vec<Example> {
-        vec![Example {
-            description: "Export the in-memory sqlite database",
-            example: "stor export --file-name nudb.sqlite",
-            result: None,
-        }]
+        vec![
+            Example {
+                description: "Export the in-memory sqlite database",
+                example: "stor export --file-name nudb.sqlite",
+                result: None,
+            },
+            Example {
+                description: "Export a named in-memory database",
+                example: "stor export --database foo --file-name foo.sqlite",
+                result: None,
+            },
+        ]
     }
 
     fn run(
@@ -63,24 +82,54 @@ impl Command for StorExport {
             }
         };
 
-        // Open the in-mem database
-        let db = Box::new(SQLiteDatabase::new(std::path::Path::new(MEMORY_DB), None));
+        let batch_size: i32 = call
+            .get_flag(engine_state, stack, "batch-size")?
+            .unwrap_or(100);
+        let database_name: Option<String> = call.get_flag(engine_state, stack, "database")?;
+
+        let target = resolve_stor_target(engine_state, database_name.as_deref(), span)?;
+        let (db, conn) = (target.db, target.conn);
+
+        let export_result = match &conn {
+            Some(shared) => {
+                let conn = shared.lock().expect("stor connection lock poisoned");
+                db.export_in_memory_database_to_file(
+                    &conn,
+                    file_name,
+                    batch_size,
+                    |remaining, pagecount| {
+                        eprintln!(
+                            "stor export: {} of {} pages remaining",
+                            remaining, pagecount
+                        );
+                    },
+                )
+            }
+            None => db.open_connection().and_then(|conn| {
+                db.export_in_memory_database_to_file(
+                    &conn,
+                    file_name,
+                    batch_size,
+                    |remaining, pagecount| {
+                        eprintln!(
+                            "stor export: {} of {} pages remaining",
+                            remaining, pagecount
+                        );
+                    },
+                )
+            }),
+        };
+
+        export_result.map_err(|err| {
+            ShellError::GenericError(
+                "Failed to export SQLite database".into(),
+                err.to_string(),
+                Some(span),
+                None,
+                Vec::new(),
+            )
+        })?;
 
-        if let Ok(conn) = db.open_connection() {
-            // This uses vacuum. I'm not really sure if this is the best way to do this.
-            // I also added backup in the sqlitedatabase impl. If we have problems, we could switch to that.
-            db.export_in_memory_database_to_file(&conn, file_name)
-                .map_err(|err| {
-                    ShellError::GenericError(
-                        "Failed to open SQLite connection in memory from export".into(),
-                        err.to_string(),
-                        Some(Span::test_data()),
-                        None,
-                        Vec::new(),
-                    )
-                })?;
-        }
-        // dbg!(db.clone());
         Ok(Value::custom_value(db, span).into_pipeline_data())
     }
 }
@@ -95,4 +144,4 @@ mod test {
 
         test_examples(StorExport {})
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,226 @@
+use crate::database::{resolve_stor_target, SQLiteDatabase};
+use nu_engine::CallExt;
+use nu_protocol::{
+    ast::Call,
+    engine::{Command, EngineState, Stack},
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, SyntaxShape, Type,
+    Value,
+};
+use rusqlite::{backup::Backup, Connection, OpenFlags};
+
+#[derive(Clone)]
+pub struct StorImport;
+
+impl Command for StorImport {
+    fn name(&self) -> &str {
+        "stor import"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("stor import")
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![]))])
+            .required_named(
+                "file-name",
+                SyntaxShape::String,
+                "the path of a sqlite file to copy into the in-memory database",
+                Some('f'),
+            )
+            .named(
+                "database",
+                SyntaxShape::String,
+                "name of a named in-memory database to import into, instead of the default store",
+                Some('d'),
+            )
+            .switch(
+                "recover",
+                "if the file fails its integrity check, move it aside and start a fresh database instead",
+                None,
+            )
+            .allow_variants_without_examples(true)
+            .category(Category::Math)
+    }
+
+    fn usage(&self) -> &str {
+        "Import a sqlite database file into the in-memory sqlite database"
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["sqlite", "open", "load", "database", "file"]
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Import a sqlite file into the in-memory database",
+                example: "stor import --file-name nudb.sqlite",
+                result: None,
+            },
+            Example {
+                description: "Import a sqlite file into a named in-memory database",
+                example: "stor import --database foo --file-name foo.sqlite",
+                result: None,
+            },
+            Example {
+                description: "Import a file, starting fresh if it turns out to be corrupt",
+                example: "stor import --file-name nudb.sqlite --recover",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let file_name_opt: Option<String> = call.get_flag(engine_state, stack, "file-name")?;
+        let file_name = match file_name_opt {
+            Some(file_name) => file_name,
+            None => {
+                return Err(ShellError::MissingParameter {
+                    param_name: "please supply a file name with the --file-name parameter".into(),
+                    span,
+                })
+            }
+        };
+
+        if !std::path::Path::new(&file_name).exists() {
+            return Err(ShellError::GenericError(
+                format!("Cannot import from {file_name}"),
+                "file does not exist".into(),
+                Some(span),
+                None,
+                Vec::new(),
+            ));
+        }
+
+        let src = Connection::open_with_flags(&file_name, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|err| {
+                ShellError::GenericError(
+                    format!("Failed to open {file_name} as a SQLite database"),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+
+        let recover = call.has_flag(engine_state, stack, "recover")?;
+        // A genuinely garbage/truncated file makes `integrity_check` itself
+        // fail (e.g. "file is not a database") rather than returning
+        // `Ok(false)` — that's unhealthy too, not a hard error.
+        let src_healthy = SQLiteDatabase::integrity_check(&src).unwrap_or(false);
+
+        if !src_healthy && !recover {
+            return Err(ShellError::GenericError(
+                format!("{file_name} failed its integrity check"),
+                "pass --recover to move it aside and start a fresh database instead".into(),
+                Some(span),
+                None,
+                Vec::new(),
+            ));
+        }
+
+        let database_name: Option<String> = call.get_flag(engine_state, stack, "database")?;
+
+        let target = resolve_stor_target(engine_state, database_name.as_deref(), span)?;
+        if target.read_only {
+            return Err(ShellError::GenericError(
+                format!("Cannot import into {}", target.db.path.display()),
+                "it was opened with `stor open --read-only`".into(),
+                Some(span),
+                None,
+                Vec::new(),
+            ));
+        }
+        let (db, shared_conn) = (target.db, target.conn);
+
+        let mut owned_dst;
+        let mut guard;
+        let dst: &mut Connection = match &shared_conn {
+            Some(shared) => {
+                guard = shared.lock().expect("stor connection lock poisoned");
+                &mut guard
+            }
+            None => {
+                owned_dst = db.open_connection().map_err(|err| {
+                    ShellError::GenericError(
+                        "Failed to open SQLite connection in memory from import".into(),
+                        err.to_string(),
+                        Some(span),
+                        None,
+                        Vec::new(),
+                    )
+                })?;
+                &mut owned_dst
+            }
+        };
+
+        if src_healthy {
+            // Copy every page from the file straight into the destination
+            // database rather than reading and re-executing SQL.
+            let backup = Backup::new(&src, dst).map_err(|err| {
+                ShellError::GenericError(
+                    format!("{file_name} is not a valid SQLite database"),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+
+            backup.step(-1).map_err(|err| {
+                ShellError::GenericError(
+                    format!("{file_name} is not a valid SQLite database"),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+        } else {
+            // `--recover`: the source is corrupt, so there's nothing safe
+            // to copy. Move it aside so it isn't lost, then reset the
+            // destination to a genuinely fresh, empty database, even if it
+            // was a named or active backend with prior tables of its own.
+            drop(src);
+            SQLiteDatabase::rename_corrupt_file_aside(std::path::Path::new(&file_name)).map_err(
+                |err| {
+                    ShellError::GenericError(
+                        format!("Failed to move aside corrupt database {file_name}"),
+                        err.to_string(),
+                        Some(span),
+                        None,
+                        Vec::new(),
+                    )
+                },
+            )?;
+            SQLiteDatabase::reset_database(dst).map_err(|err| {
+                ShellError::GenericError(
+                    "Failed to initialize a fresh database".into(),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+        }
+
+        Ok(Value::custom_value(db, span).into_pipeline_data())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        use crate::test_examples;
+
+        test_examples(StorImport {})
+    }
+}
@@ -0,0 +1,7 @@
+mod export;
+mod import;
+mod open;
+
+pub use export::StorExport;
+pub use import::StorImport;
+pub use open::StorOpen;
@@ -0,0 +1,184 @@
+use crate::database::SQLiteDatabase;
+use nu_engine::CallExt;
+use nu_protocol::{
+    ast::Call,
+    engine::{Command, EngineState, Stack},
+    Category, Example, IntoPipelineData, PipelineData, ShellError, Signature, SyntaxShape, Type,
+    Value,
+};
+use rusqlite::OpenFlags;
+
+#[derive(Clone)]
+pub struct StorOpen;
+
+impl Command for StorOpen {
+    fn name(&self) -> &str {
+        "stor open"
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::build("stor open")
+            .input_output_types(vec![(Type::Nothing, Type::Table(vec![]))])
+            .required_named(
+                "file-name",
+                SyntaxShape::String,
+                "the path of a sqlite file to make the active stor backend",
+                Some('f'),
+            )
+            .switch(
+                "read-only",
+                "open the file read-only, for safe inspection of an existing database",
+                Some('r'),
+            )
+            .switch(
+                "recover",
+                "if the database fails its integrity check, move it aside and start a fresh one",
+                None,
+            )
+            .allow_variants_without_examples(true)
+            .category(Category::Math)
+    }
+
+    fn usage(&self) -> &str {
+        "Open a sqlite database file as the active stor backend"
+    }
+
+    fn search_terms(&self) -> Vec<&str> {
+        vec!["sqlite", "database", "persistent", "file", "load"]
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![
+            Example {
+                description: "Make a sqlite file the active stor backend",
+                example: "stor open --file-name nudb.sqlite",
+                result: None,
+            },
+            Example {
+                description: "Open a sqlite file read-only to inspect it",
+                example: "stor open --file-name nudb.sqlite --read-only",
+                result: None,
+            },
+            Example {
+                description: "Open a database, recreating it fresh if it's corrupt",
+                example: "stor open --file-name nudb.sqlite --recover",
+                result: None,
+            },
+        ]
+    }
+
+    fn run(
+        &self,
+        engine_state: &EngineState,
+        stack: &mut Stack,
+        call: &Call,
+        _input: PipelineData,
+    ) -> Result<PipelineData, ShellError> {
+        let span = call.head;
+        let file_name_opt: Option<String> = call.get_flag(engine_state, stack, "file-name")?;
+        let file_name = match file_name_opt {
+            Some(file_name) => file_name,
+            None => {
+                return Err(ShellError::MissingParameter {
+                    param_name: "please supply a file name with the --file-name parameter".into(),
+                    span,
+                })
+            }
+        };
+        let read_only = call.has_flag(engine_state, stack, "read-only")?;
+        let recover = call.has_flag(engine_state, stack, "recover")?;
+
+        if read_only && recover {
+            return Err(ShellError::GenericError(
+                "--read-only and --recover cannot be used together".into(),
+                "recovering a corrupt database requires creating a new file in its place".into(),
+                Some(span),
+                None,
+                Vec::new(),
+            ));
+        }
+
+        let flags = if read_only {
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+        } else {
+            OpenFlags::default()
+        };
+
+        let db = Box::new(SQLiteDatabase::new(std::path::Path::new(&file_name), None));
+        let mut conn = db.open_connection_with_flags(flags).map_err(|err| {
+            ShellError::GenericError(
+                format!("Failed to open {file_name} as a SQLite database"),
+                err.to_string(),
+                Some(span),
+                None,
+                Vec::new(),
+            )
+        })?;
+
+        // A genuinely garbage/truncated file makes `integrity_check` itself
+        // fail (e.g. "file is not a database") rather than returning
+        // `Ok(false)` — that's unhealthy too, not a hard error.
+        let healthy = SQLiteDatabase::integrity_check(&conn).unwrap_or(false);
+
+        if !healthy {
+            if !recover {
+                return Err(ShellError::GenericError(
+                    format!("{file_name} failed its integrity check"),
+                    "pass --recover to move it aside and start a fresh database".into(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                ));
+            }
+
+            // Drop the connection to the corrupt file before renaming it.
+            drop(conn);
+            SQLiteDatabase::rename_corrupt_file_aside(std::path::Path::new(&file_name)).map_err(
+                |err| {
+                    ShellError::GenericError(
+                        format!("Failed to move aside corrupt database {file_name}"),
+                        err.to_string(),
+                        Some(span),
+                        None,
+                        Vec::new(),
+                    )
+                },
+            )?;
+
+            conn = db.open_connection_with_flags(flags).map_err(|err| {
+                ShellError::GenericError(
+                    format!("Failed to create a fresh database at {file_name}"),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+            SQLiteDatabase::write_schema_version(&conn).map_err(|err| {
+                ShellError::GenericError(
+                    format!("Failed to initialize schema for {file_name}"),
+                    err.to_string(),
+                    Some(span),
+                    None,
+                    Vec::new(),
+                )
+            })?;
+        }
+
+        engine_state.set_stor_active(std::path::PathBuf::from(&file_name), conn, read_only);
+
+        Ok(Value::custom_value(db, span).into_pipeline_data())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        use crate::test_examples;
+
+        test_examples(StorOpen {})
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use rusqlite::Connection;
+
+/// The file-backed database activated by `stor open`, kept in `EngineState`
+/// so it survives across commands.
+#[derive(Clone)]
+pub struct StorActive {
+    pub path: PathBuf,
+    pub conn: Arc<Mutex<Connection>>,
+    /// Whether `stor open` was given `--read-only`. Commands that write
+    /// (e.g. `stor import`) must check this and refuse instead of letting
+    /// the write fail deep inside SQLite with a confusing error.
+    pub read_only: bool,
+}
+
+/// The core engine state shared across pipeline evaluations within a single
+/// Nushell session.
+#[derive(Clone)]
+pub struct EngineState {
+    /// Named in-memory `stor` databases, keyed by the name passed to
+    /// `--database`. SQLite only keeps a shared-cache in-memory database
+    /// alive as long as at least one connection to it is open, so we hold
+    /// one open here for the lifetime of the engine rather than re-opening
+    /// (and losing) the data on every `stor` call.
+    stor_connections: Arc<Mutex<HashMap<String, Arc<Mutex<Connection>>>>>,
+
+    /// The file-backed database opened by `stor open`, if any, which acts
+    /// as the active backend for subsequent `stor` commands in place of the
+    /// default in-memory store.
+    stor_active: Arc<Mutex<Option<StorActive>>>,
+}
+
+impl EngineState {
+    pub fn new() -> Self {
+        Self {
+            stor_connections: Arc::new(Mutex::new(HashMap::new())),
+            stor_active: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Get the shared connection for a named `stor` database, opening and
+    /// caching a shared-cache in-memory connection the first time the name
+    /// is seen.
+    pub fn stor_connection(&self, name: &str) -> Result<Arc<Mutex<Connection>>, rusqlite::Error> {
+        let mut connections = self
+            .stor_connections
+            .lock()
+            .expect("stor connections lock poisoned");
+
+        if let Some(conn) = connections.get(name) {
+            return Ok(Arc::clone(conn));
+        }
+
+        let uri = format!("file:{name}?mode=memory&cache=shared");
+        let conn = Arc::new(Mutex::new(Connection::open(uri)?));
+        connections.insert(name.to_string(), Arc::clone(&conn));
+        Ok(conn)
+    }
+
+    /// Make `conn` (backed by the file at `path`) the active `stor` backend,
+    /// replacing whatever `stor open` previously activated. `read_only`
+    /// records whether `conn` was opened with `--read-only`, so later
+    /// commands can refuse to write through it.
+    pub fn set_stor_active(&self, path: PathBuf, conn: Connection, read_only: bool) {
+        let mut active = self.stor_active.lock().expect("stor active lock poisoned");
+        *active = Some(StorActive {
+            path,
+            conn: Arc::new(Mutex::new(conn)),
+            read_only,
+        });
+    }
+
+    /// The database activated by `stor open`, if `stor open` has been run
+    /// this session.
+    pub fn stor_active(&self) -> Option<StorActive> {
+        self.stor_active
+            .lock()
+            .expect("stor active lock poisoned")
+            .clone()
+    }
+}
+
+impl Default for EngineState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn named_stor_database_persists_across_calls() {
+        let engine_state = EngineState::new();
+
+        let first = engine_state
+            .stor_connection("shared_test_db")
+            .expect("open named db the first time");
+        first
+            .lock()
+            .unwrap()
+            .execute("CREATE TABLE foo (x INTEGER)", [])
+            .expect("create table");
+        first
+            .lock()
+            .unwrap()
+            .execute("INSERT INTO foo (x) VALUES (42)", [])
+            .expect("insert row");
+
+        // A second, independent call with the same name should see the data
+        // the first call wrote, because both resolve to the same cached
+        // connection rather than opening a fresh shared-cache database.
+        let second = engine_state
+            .stor_connection("shared_test_db")
+            .expect("open named db the second time");
+        let value: i64 = second
+            .lock()
+            .unwrap()
+            .query_row("SELECT x FROM foo", [], |row| row.get(0))
+            .expect("read row written by the first call");
+        assert_eq!(value, 42);
+
+        // A different name must not see the same data.
+        let other = engine_state
+            .stor_connection("other_test_db")
+            .expect("open a different named db");
+        let missing = other.lock().unwrap().query_row(
+            "SELECT x FROM foo",
+            [],
+            |row: &rusqlite::Row| -> rusqlite::Result<i64> { row.get(0) },
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn stor_active_reports_the_path_it_was_opened_with() {
+        let engine_state = EngineState::new();
+        assert!(engine_state.stor_active().is_none());
+
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        let path = PathBuf::from("nudb.sqlite");
+        engine_state.set_stor_active(path.clone(), conn, false);
+
+        let active = engine_state.stor_active().expect("active db is set");
+        assert_eq!(active.path, path);
+        assert!(!active.read_only);
+    }
+
+    #[test]
+    fn stor_active_reports_whether_it_was_opened_read_only() {
+        let engine_state = EngineState::new();
+
+        let conn = Connection::open_in_memory().expect("open in-memory db");
+        engine_state.set_stor_active(PathBuf::from("nudb.sqlite"), conn, true);
+
+        let active = engine_state.stor_active().expect("active db is set");
+        assert!(active.read_only);
+    }
+}
@@ -0,0 +1,3 @@
+mod engine_state;
+
+pub use engine_state::{EngineState, StorActive};